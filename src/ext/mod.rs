@@ -0,0 +1,18 @@
+//! Ready-made [`GuardAction`](crate::GuardAction)s for tying a stream's
+//! lifetime directly to a resource from a common async ecosystem, each
+//! gated behind its own cargo feature.
+
+#[cfg(feature = "tokio1-task")]
+mod tokio1_task;
+#[cfg(feature = "tokio1-task")]
+pub use tokio1_task::*;
+
+#[cfg(feature = "tokio1-sync")]
+mod tokio1_sync;
+#[cfg(feature = "tokio1-sync")]
+pub use tokio1_sync::*;
+
+#[cfg(feature = "triggered")]
+mod triggered;
+#[cfg(feature = "triggered")]
+pub use triggered::*;
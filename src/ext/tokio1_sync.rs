@@ -0,0 +1,59 @@
+//! [`GuardAction`]s for [`tokio::sync`] primitives.
+
+use std::sync::Arc;
+
+use tokio::sync::{oneshot, Semaphore};
+
+use crate::GuardAction;
+
+/// A [`GuardAction`] that sends a `()` over its [`oneshot::Sender`] when run,
+/// ignoring the case where the receiver has already been dropped.
+pub struct NotifyOnDrop(oneshot::Sender<()>);
+
+impl GuardAction for NotifyOnDrop {
+    fn run(self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// Wraps the given sender so a `()` is sent on it once the guard runs.
+pub fn notify_on_drop(sender: oneshot::Sender<()>) -> NotifyOnDrop {
+    NotifyOnDrop(sender)
+}
+
+/// A [`GuardAction`] that closes its [`Semaphore`] when run.
+pub struct CloseOnDrop(Arc<Semaphore>);
+
+impl GuardAction for CloseOnDrop {
+    fn run(self) {
+        self.0.close();
+    }
+}
+
+/// Wraps the given semaphore so it is closed once the guard runs.
+pub fn close_on_drop(semaphore: Arc<Semaphore>) -> CloseOnDrop {
+    CloseOnDrop(semaphore)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn notify_on_drop_sends_on_the_channel() {
+        let (tx, rx) = oneshot::channel();
+
+        notify_on_drop(tx).run();
+
+        assert_eq!(rx.await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn close_on_drop_closes_the_semaphore() {
+        let semaphore = Arc::new(Semaphore::new(1));
+
+        close_on_drop(semaphore.clone()).run();
+
+        assert!(semaphore.is_closed());
+    }
+}
@@ -0,0 +1,36 @@
+//! A [`GuardAction`] that aborts a [`tokio::task::JoinHandle`].
+
+use tokio::task::JoinHandle;
+
+use crate::GuardAction;
+
+/// A [`GuardAction`] that aborts its [`JoinHandle`] when run.
+pub struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> GuardAction for AbortOnDrop<T> {
+    fn run(self) {
+        self.0.abort();
+    }
+}
+
+/// Wraps the given task handle so it is aborted once the guard runs,
+/// tying the task's lifetime to the guarded stream or future.
+pub fn abort_on_drop<T>(handle: JoinHandle<T>) -> AbortOnDrop<T> {
+    AbortOnDrop(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn abort_on_drop_aborts_the_task() {
+        let handle = tokio::spawn(std::future::pending::<()>());
+        let abort_handle = handle.abort_handle();
+
+        abort_on_drop(handle).run();
+        tokio::task::yield_now().await;
+
+        assert!(abort_handle.is_finished());
+    }
+}
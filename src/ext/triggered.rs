@@ -0,0 +1,33 @@
+//! A [`GuardAction`] for [`triggered::Trigger`].
+
+use triggered::Trigger;
+
+use crate::GuardAction;
+
+/// A [`GuardAction`] that fires its [`Trigger`] when run.
+pub struct TriggerOnDrop(Trigger);
+
+impl GuardAction for TriggerOnDrop {
+    fn run(self) {
+        self.0.trigger();
+    }
+}
+
+/// Wraps the given trigger so it is fired once the guard runs.
+pub fn trigger_on_drop(trigger: Trigger) -> TriggerOnDrop {
+    TriggerOnDrop(trigger)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trigger_on_drop_fires_the_trigger() {
+        let (trigger, listener) = ::triggered::trigger();
+
+        trigger_on_drop(trigger).run();
+
+        assert!(listener.is_triggered());
+    }
+}
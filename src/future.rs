@@ -0,0 +1,161 @@
+//! A [`Future`] counterpart to [`StreamGuard`](crate::StreamGuard), for
+//! running an action if a future is dropped before it resolves.
+
+use std::{future::Future, pin::Pin, task::{Context, Poll}};
+
+use pin_project::{pin_project, pinned_drop};
+
+use crate::{GuardAction, GuardMode};
+
+/// A [`Future`] wrapper that automatically runs a custom [`GuardAction`] when
+/// dropped.
+#[pin_project(PinnedDrop)]
+pub struct FutureGuard<Fut, A> where Fut: Future, A: GuardAction {
+    #[pin]
+    future: Fut,
+    on_drop: Option<A>,
+    completed: bool,
+    mode: GuardMode,
+}
+
+impl<Fut, A> FutureGuard<Fut, A> where Fut: Future, A: GuardAction {
+    /// Wraps the given [`Future`], running the given action upon being dropped.
+    pub fn new(future: Fut, on_drop: A) -> Self {
+        Self::with_mode(future, on_drop, GuardMode::Always)
+    }
+
+    /// Wraps the given [`Future`], running the given action only if the
+    /// future is dropped before it resolves.
+    pub fn new_on_cancel(future: Fut, on_drop: A) -> Self {
+        Self::with_mode(future, on_drop, GuardMode::OnCancel)
+    }
+
+    fn with_mode(future: Fut, on_drop: A, mode: GuardMode) -> Self {
+        Self { future, on_drop: Some(on_drop), completed: false, mode }
+    }
+
+    /// Consumes the guard, returning the wrapped future without running the
+    /// `on_drop` action.
+    ///
+    /// This is the escape hatch for callers who decide that the cleanup is no
+    /// longer warranted, e.g. because the future is being handed off
+    /// elsewhere.
+    pub fn disarm(self) -> Fut {
+        self.into_inner().0
+    }
+
+    /// Consumes the guard, returning the wrapped future and the `on_drop`
+    /// action without running it.
+    ///
+    /// Like [`disarm`](Self::disarm), but also hands back the action in case
+    /// the caller wants to run it manually or inspect it.
+    pub fn into_inner(self) -> (Fut, Option<A>) {
+        // `FutureGuard` has a `PinnedDrop` impl, so we cannot destructure it
+        // by value directly. Instead we wrap it in `ManuallyDrop` so its
+        // `drop` glue never runs, then move the fields out by raw pointer
+        // read, which is sound since `self` is otherwise never touched again.
+        let this = std::mem::ManuallyDrop::new(self);
+        let future = unsafe { std::ptr::read(&this.future) };
+        let on_drop = unsafe { std::ptr::read(&this.on_drop) };
+        (future, on_drop)
+    }
+}
+
+impl<Fut, A> Future for FutureGuard<Fut, A> where Fut: Future, A: GuardAction {
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let poll = this.future.poll(cx);
+        if poll.is_ready() {
+            *this.completed = true;
+        }
+        poll
+    }
+}
+
+#[pinned_drop]
+impl<Fut, A> PinnedDrop for FutureGuard<Fut, A> where Fut: Future, A: GuardAction {
+    fn drop(mut self: Pin<&mut Self>) {
+        let this = self.project();
+        let should_run = match this.mode {
+            GuardMode::Always => true,
+            GuardMode::OnCancel => !*this.completed,
+        };
+        let on_drop = this.on_drop.take().expect("No on_drop action in FutureGuard, was drop called twice or constructed wrongly?");
+        if should_run {
+            on_drop.run()
+        }
+    }
+}
+
+/// A convenience extension for creating a [`FutureGuard`] via a method.
+pub trait GuardFutureExt: Future + Sized {
+    /// Wraps the [`Future`], running the given action upon being dropped.
+    fn guard<A>(self, on_drop: A) -> FutureGuard<Self, A> where A: GuardAction;
+
+    /// Wraps the [`Future`], running the given action only if the future is
+    /// dropped before it resolves.
+    fn guard_on_cancel<A>(self, on_drop: A) -> FutureGuard<Self, A> where A: GuardAction;
+}
+
+impl<Fut> GuardFutureExt for Fut where Fut: Future + Sized {
+    fn guard<A>(self, on_drop: A) -> FutureGuard<Self, A> where A: GuardAction {
+        FutureGuard::new(self, on_drop)
+    }
+
+    fn guard_on_cancel<A>(self, on_drop: A) -> FutureGuard<Self, A> where A: GuardAction {
+        FutureGuard::new_on_cancel(self, on_drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use futures::executor::block_on;
+    use futures::future;
+
+    use super::*;
+
+    #[test]
+    fn guard_fires_when_dropped_before_resolution() {
+        let ran = AtomicBool::new(false);
+        drop(future::pending::<()>().guard(|| ran.store(true, Ordering::SeqCst)));
+        assert!(ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn guard_on_cancel_does_not_fire_after_resolution() {
+        let ran = AtomicBool::new(false);
+        let guard = future::ready(()).guard_on_cancel(|| ran.store(true, Ordering::SeqCst));
+        block_on(guard);
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn guard_on_cancel_fires_when_dropped_before_resolution() {
+        let ran = AtomicBool::new(false);
+        drop(future::pending::<()>().guard_on_cancel(|| ran.store(true, Ordering::SeqCst)));
+        assert!(ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn disarm_returns_future_without_running_on_drop() {
+        let ran = AtomicBool::new(false);
+        let guard = future::pending::<()>().guard(|| ran.store(true, Ordering::SeqCst));
+        drop(guard.disarm());
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn into_inner_returns_future_and_unfired_action() {
+        let ran = AtomicBool::new(false);
+        let guard = future::ready(1).guard(|| ran.store(true, Ordering::SeqCst));
+
+        let (fut, on_drop) = guard.into_inner();
+        assert!(on_drop.is_some());
+        assert!(!ran.load(Ordering::SeqCst));
+        assert_eq!(block_on(fut), 1);
+    }
+}
@@ -1,8 +1,8 @@
 //! A small RAII wrapper around a [`Stream`] that automatically invokes a
 //! user-defined action upon being dropped.
-//! 
+//!
 //! For example:
-//! 
+//!
 //! ```rust
 //! # use futures::stream::{self, StreamExt};
 //! # use stream_guard::GuardStreamExt;
@@ -14,9 +14,9 @@
 //!     }
 //! }
 //! ```
-//! 
+//!
 //! would print
-//! 
+//!
 //! ```plaintext
 //! 0
 //! 1
@@ -24,31 +24,170 @@
 //! Dropped!
 //! ```
 
+#[cfg(any(feature = "tokio1-task", feature = "tokio1-sync", feature = "triggered"))]
+pub mod ext;
+mod future;
+
+pub use future::{FutureGuard, GuardFutureExt};
+
 use std::{pin::Pin, task::{Context, Poll}};
 
-use futures::Stream;
+use futures::{Stream, StreamExt, stream::Fuse};
 use pin_project::{pin_project, pinned_drop};
 
-/// A [`Stream`] wrapper that automatically runs a custom action when dropped.
+/// An action that can be run exactly once, e.g. to tear down a resource whose
+/// lifetime is tied to a [`StreamGuard`].
+///
+/// This is implemented for any `FnOnce()` via a blanket impl, so plain
+/// closures keep working with [`StreamGuard`] and [`GuardStreamExt`]; it also
+/// lets the crate offer ready-made actions for common resources, see the
+/// `ext` module (behind its cargo features).
+pub trait GuardAction {
+    /// Runs the action, consuming it.
+    fn run(self);
+}
+
+impl<F: FnOnce()> GuardAction for F {
+    fn run(self) {
+        self()
+    }
+}
+
+/// Determines when a guard's `on_drop` action should run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GuardMode {
+    /// Run the action unconditionally, whether the stream completed or not.
+    Always,
+    /// Only run the action if the stream was dropped before yielding `None`.
+    OnCancel,
+}
+
+/// A summary of how a guarded stream ran, passed to the closures registered
+/// via [`StreamGuard::new_with_stats`]/[`StreamGuard::new_on_cancel_with_stats`]
+/// (or the matching [`GuardStreamExt`] methods).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamGuardStats {
+    /// How many items the stream yielded before it was dropped or completed.
+    pub items_yielded: usize,
+    /// Whether the stream ran to completion, i.e. yielded `None`.
+    pub completed: bool,
+}
+
+/// The action a [`StreamGuard`] runs when dropped, given a [`StreamGuardStats`]
+/// summary of how the stream ran.
+///
+/// This is implemented for any [`GuardAction`] (which simply ignores the
+/// stats), so [`StreamGuard`] stays backward compatible with plain actions;
+/// closures that want the stats go through [`StreamGuard::new_with_stats`]
+/// instead, which wraps them in [`StatsFn`].
+pub trait StreamGuardAction {
+    /// Runs the action, consuming it.
+    fn run(self, stats: StreamGuardStats);
+}
+
+impl<A: GuardAction> StreamGuardAction for A {
+    fn run(self, _stats: StreamGuardStats) {
+        GuardAction::run(self)
+    }
+}
+
+/// Adapts an `FnOnce(StreamGuardStats)` closure into a [`StreamGuardAction`].
+///
+/// Constructed by [`StreamGuard::new_with_stats`] and
+/// [`StreamGuard::new_on_cancel_with_stats`]; not meant to be built directly.
+pub struct StatsFn<F>(F);
+
+impl<F: FnOnce(StreamGuardStats)> StreamGuardAction for StatsFn<F> {
+    fn run(self, stats: StreamGuardStats) {
+        (self.0)(stats)
+    }
+}
+
+/// A [`Stream`] wrapper that automatically runs a custom [`StreamGuardAction`]
+/// when dropped.
+///
+/// The stream is fused internally so that `completed` latches permanently
+/// once the inner stream yields `None`, even if it were polled again.
 #[pin_project(PinnedDrop)]
-pub struct StreamGuard<S, F> where S: Stream, F: FnOnce() {
+pub struct StreamGuard<S, A> where S: Stream, A: StreamGuardAction {
     #[pin]
-    stream: S,
-    on_drop: Option<F>,
+    stream: Fuse<S>,
+    on_drop: Option<A>,
+    items_yielded: usize,
+    completed: bool,
+    mode: GuardMode,
 }
 
-impl<S, F> StreamGuard<S, F> where S: Stream, F: FnOnce() {
-    /// Wraps the given [`Stream`], running the given closure upon being dropped.
-    pub fn new(stream: S, on_drop: F) -> Self {
-        Self { stream, on_drop: Some(on_drop) }
+impl<S, A> StreamGuard<S, A> where S: Stream, A: StreamGuardAction {
+    /// Wraps the given [`Stream`], running the given action upon being dropped.
+    pub fn new(stream: S, on_drop: A) -> Self {
+        Self::with_mode(stream, on_drop, GuardMode::Always)
+    }
+
+    /// Wraps the given [`Stream`], running the given action only if the
+    /// stream is dropped before it yields `None`, i.e. before it completes.
+    pub fn new_on_cancel(stream: S, on_drop: A) -> Self {
+        Self::with_mode(stream, on_drop, GuardMode::OnCancel)
+    }
+
+    fn with_mode(stream: S, on_drop: A, mode: GuardMode) -> Self {
+        Self { stream: stream.fuse(), on_drop: Some(on_drop), items_yielded: 0, completed: false, mode }
+    }
+
+    /// Consumes the guard, returning the wrapped stream without running the
+    /// `on_drop` action.
+    ///
+    /// This is the escape hatch for callers who decide that the cleanup is no
+    /// longer warranted, e.g. because the stream is being handed off
+    /// elsewhere.
+    pub fn disarm(self) -> S {
+        self.into_inner().0
+    }
+
+    /// Consumes the guard, returning the wrapped stream and the `on_drop`
+    /// action without running it.
+    ///
+    /// Like [`disarm`](Self::disarm), but also hands back the action in case
+    /// the caller wants to run it manually or inspect it.
+    pub fn into_inner(self) -> (S, Option<A>) {
+        // `StreamGuard` has a `PinnedDrop` impl, so we cannot destructure it
+        // by value directly. Instead we wrap it in `ManuallyDrop` so its
+        // `drop` glue never runs, then move the fields out by raw pointer
+        // read, which is sound since `self` is otherwise never touched again.
+        let this = std::mem::ManuallyDrop::new(self);
+        let stream = unsafe { std::ptr::read(&this.stream) };
+        let on_drop = unsafe { std::ptr::read(&this.on_drop) };
+        (stream.into_inner(), on_drop)
     }
 }
 
-impl<S, F> Stream for StreamGuard<S, F> where S: Stream, F: FnOnce() {
+impl<S, F> StreamGuard<S, StatsFn<F>> where S: Stream, F: FnOnce(StreamGuardStats) {
+    /// Wraps the given [`Stream`], running the given closure upon being
+    /// dropped with a [`StreamGuardStats`] summary of how the stream ran.
+    pub fn new_with_stats(stream: S, on_drop: F) -> Self {
+        Self::with_mode(stream, StatsFn(on_drop), GuardMode::Always)
+    }
+
+    /// Wraps the given [`Stream`], running the given closure only if the
+    /// stream is dropped before it completes, passing a [`StreamGuardStats`]
+    /// summary of how the stream ran.
+    pub fn new_on_cancel_with_stats(stream: S, on_drop: F) -> Self {
+        Self::with_mode(stream, StatsFn(on_drop), GuardMode::OnCancel)
+    }
+}
+
+impl<S, A> Stream for StreamGuard<S, A> where S: Stream, A: StreamGuardAction {
     type Item = S::Item;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.project().stream.poll_next(cx)
+        let this = self.project();
+        let poll = this.stream.poll_next(cx);
+        match poll {
+            Poll::Ready(Some(_)) => *this.items_yielded += 1,
+            Poll::Ready(None) => *this.completed = true,
+            Poll::Pending => {}
+        }
+        poll
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -57,20 +196,134 @@ impl<S, F> Stream for StreamGuard<S, F> where S: Stream, F: FnOnce() {
 }
 
 #[pinned_drop]
-impl<S, F> PinnedDrop for StreamGuard<S, F> where S: Stream, F: FnOnce() {
+impl<S, A> PinnedDrop for StreamGuard<S, A> where S: Stream, A: StreamGuardAction {
     fn drop(mut self: Pin<&mut Self>) {
-        self.project().on_drop.take().expect("No on_drop function in StreamGuard, was drop called twice or constructed wrongly?")()
+        let this = self.project();
+        let stats = StreamGuardStats { items_yielded: *this.items_yielded, completed: *this.completed };
+        let should_run = match this.mode {
+            GuardMode::Always => true,
+            GuardMode::OnCancel => !stats.completed,
+        };
+        let on_drop = this.on_drop.take().expect("No on_drop action in StreamGuard, was drop called twice or constructed wrongly?");
+        if should_run {
+            on_drop.run(stats)
+        }
     }
 }
 
 /// A convenience extension for creating a [`StreamGuard`] via a method.
 pub trait GuardStreamExt: Stream + Sized {
-    /// Wraps the [`Stream`], running the given closure upon being dropped.
-    fn guard<F>(self, on_drop: F) -> StreamGuard<Self, F> where F: FnOnce();
+    /// Wraps the [`Stream`], running the given action upon being dropped.
+    fn guard<A>(self, on_drop: A) -> StreamGuard<Self, A> where A: StreamGuardAction;
+
+    /// Wraps the [`Stream`], running the given action only if the stream is
+    /// dropped before it completes, i.e. before it yields `None`.
+    fn guard_on_cancel<A>(self, on_drop: A) -> StreamGuard<Self, A> where A: StreamGuardAction;
+
+    /// Wraps the [`Stream`], running the given closure upon being dropped
+    /// with a [`StreamGuardStats`] summary of how the stream ran.
+    fn guard_with_stats<F>(self, on_drop: F) -> StreamGuard<Self, StatsFn<F>> where F: FnOnce(StreamGuardStats);
+
+    /// Wraps the [`Stream`], running the given closure only if the stream is
+    /// dropped before it completes, passing a [`StreamGuardStats`] summary of
+    /// how the stream ran.
+    fn guard_on_cancel_with_stats<F>(self, on_drop: F) -> StreamGuard<Self, StatsFn<F>> where F: FnOnce(StreamGuardStats);
 }
 
 impl<S> GuardStreamExt for S where S: Stream + Sized {
-    fn guard<F>(self, on_drop: F) -> StreamGuard<Self, F> where F: FnOnce() {
+    fn guard<A>(self, on_drop: A) -> StreamGuard<Self, A> where A: StreamGuardAction {
         StreamGuard::new(self, on_drop)
     }
+
+    fn guard_on_cancel<A>(self, on_drop: A) -> StreamGuard<Self, A> where A: StreamGuardAction {
+        StreamGuard::new_on_cancel(self, on_drop)
+    }
+
+    fn guard_with_stats<F>(self, on_drop: F) -> StreamGuard<Self, StatsFn<F>> where F: FnOnce(StreamGuardStats) {
+        StreamGuard::new_with_stats(self, on_drop)
+    }
+
+    fn guard_on_cancel_with_stats<F>(self, on_drop: F) -> StreamGuard<Self, StatsFn<F>> where F: FnOnce(StreamGuardStats) {
+        StreamGuard::new_on_cancel_with_stats(self, on_drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    use super::*;
+
+    #[test]
+    fn disarm_returns_stream_without_running_on_drop() {
+        let ran = AtomicBool::new(false);
+        let guard = stream::iter(0..3).guard(|| ran.store(true, Ordering::SeqCst));
+        let mut stream = guard.disarm();
+        assert_eq!(block_on(stream.next()), Some(0));
+        drop(stream);
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn into_inner_returns_stream_and_unfired_action() {
+        let ran = AtomicBool::new(false);
+        let mut guard = stream::iter(0..3).guard(|| ran.store(true, Ordering::SeqCst));
+        assert_eq!(block_on(guard.next()), Some(0));
+
+        let (mut stream, on_drop) = guard.into_inner();
+        assert!(on_drop.is_some());
+        assert!(!ran.load(Ordering::SeqCst));
+        assert_eq!(block_on(stream.next()), Some(1));
+    }
+
+    #[test]
+    fn guard_on_cancel_fires_when_dropped_before_completion() {
+        let ran = AtomicBool::new(false);
+        {
+            let mut guard = stream::iter(0..3).guard_on_cancel(|| ran.store(true, Ordering::SeqCst));
+            assert_eq!(block_on(guard.next()), Some(0));
+        }
+        assert!(ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn guard_on_cancel_does_not_fire_after_completion() {
+        let ran = AtomicBool::new(false);
+        {
+            let mut guard = stream::iter(0..1).guard_on_cancel(|| ran.store(true, Ordering::SeqCst));
+            assert_eq!(block_on(guard.next()), Some(0));
+            assert_eq!(block_on(guard.next()), None);
+        }
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn stats_are_accurate_when_dropped_before_completion() {
+        let stats = std::cell::Cell::new(None);
+        {
+            let mut guard = stream::iter(0..5).guard_with_stats(|s| stats.set(Some(s)));
+            assert_eq!(block_on(guard.next()), Some(0));
+            assert_eq!(block_on(guard.next()), Some(1));
+        }
+        let stats = stats.get().expect("on_drop should have run");
+        assert_eq!(stats.items_yielded, 2);
+        assert!(!stats.completed);
+    }
+
+    #[test]
+    fn stats_are_accurate_when_stream_completes() {
+        let stats = std::cell::Cell::new(None);
+        {
+            let mut guard = stream::iter(0..2).guard_with_stats(|s| stats.set(Some(s)));
+            assert_eq!(block_on(guard.next()), Some(0));
+            assert_eq!(block_on(guard.next()), Some(1));
+            assert_eq!(block_on(guard.next()), None);
+        }
+        let stats = stats.get().expect("on_drop should have run");
+        assert_eq!(stats.items_yielded, 2);
+        assert!(stats.completed);
+    }
 }